@@ -0,0 +1,3 @@
+//! The `std` I/O error, made available under the same `crate::io` path as its stand-in in
+//! `io_nostd.rs`. Nothing in this crate uses it while the `std` feature is enabled.
+pub use std::io::Error;
@@ -0,0 +1,13 @@
+//! A minimal stand-in for [`std::io::Error`] used when this crate is built without `std`, where
+//! there is no filesystem to read a config from in the first place. It is what `decode::Error::Io`
+//! carries when `as_path` is called without `std`.
+use core::fmt;
+
+#[derive(Debug)]
+pub struct Error;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("I/O is not available without the `std` feature")
+    }
+}
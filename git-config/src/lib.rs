@@ -1,7 +1,28 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![forbid(unsafe_code)]
 // #![forbid(rust_2018_idioms)]
 
-use std::ops::Range;
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(feature = "std")]
+#[path = "io.rs"]
+mod io;
+#[cfg(not(feature = "std"))]
+#[path = "io_nostd.rs"]
+mod io;
+
+/// Re-exports the handful of allocating types this crate needs from either `std` or `alloc`,
+/// depending on which is active, so the rest of the crate doesn't have to juggle the two.
+mod no_std_prelude {
+    #[cfg(feature = "std")]
+    pub use std::{borrow::Cow, boxed::Box, vec::Vec};
+
+    #[cfg(not(feature = "std"))]
+    pub use alloc::{borrow::Cow, boxed::Box, vec::Vec};
+}
+
+use core::ops::Range;
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
 struct Span {
@@ -25,93 +46,636 @@ impl Span {
 }
 
 mod file {
-    use crate::{spanned, Span};
+    use crate::{borrowed, no_std_prelude::Vec, spanned, Span};
     use bstr::{BStr, ByteSlice};
+    use core::cmp::Ordering;
+    use once_cell::race::OnceBox;
 
-    enum Token {
+    pub(crate) enum Token {
         Section(spanned::Section),
         Entry(spanned::Entry),
         Comment(spanned::Comment),
     }
 
+    /// A `(section token index, entry token index)` pair together with the spans needed to order
+    /// and compare it against a query, without ever copying bytes out of `File::buf`.
+    struct IndexEntry {
+        section: Span,
+        sub_name: Option<Span>,
+        key: Span,
+        section_token: usize,
+        entry_token: usize,
+    }
+
+    /// Sorted views over `tokens`, keyed by the same case folding rules git applies to section,
+    /// subsection and key names, so repeated lookups can binary search instead of walking and
+    /// re-decoding `tokens` every time. Built lazily since most files are read once and never
+    /// looked up by key at all.
+    ///
+    /// Entries are sorted `Span`s into the file's own buffer rather than owned, lowercased copies:
+    /// comparisons fold case at comparison time instead of paying an allocation up front for every
+    /// section/subsection/key pair, and again for every lookup.
+    #[derive(Default)]
+    struct Index {
+        /// Every `(section, subsection)` pair that was ever opened, regardless of whether it holds
+        /// entries, sorted the same way `entries` is.
+        sections: Vec<(Span, Option<Span>)>,
+        /// All entries across the whole file, sorted by `(section, sub_name, key)`.
+        entries: Vec<IndexEntry>,
+    }
+
+    /// Case-insensitive ordering for section and key names, matching how git compares them.
+    fn cmp_ci(a: &[u8], b: &[u8]) -> Ordering {
+        a.iter().map(u8::to_ascii_lowercase).cmp(b.iter().map(u8::to_ascii_lowercase))
+    }
+
+    /// Case-sensitive ordering for subsection names, matching how git compares them (e.g.
+    /// `[remote "origin"]` and `[remote "Origin"]` are distinct).
+    fn cmp_sub_name(a: Option<&[u8]>, b: Option<&[u8]>) -> Ordering {
+        match (a, b) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => Ordering::Less,
+            (Some(_), None) => Ordering::Greater,
+            (Some(a), Some(b)) => a.cmp(b),
+        }
+    }
+
     pub struct File {
         buf: Vec<u8>,
-        tokens: Vec<Token>, // but how do we get fast lookups and proper value lookup based on decoded values?
-                            // On the fly is easier, otherwise we have to deal with a lookup cache of sorts and
-                            // many more allocations up front (which might be worth it). Cow<'a, _> would bind to
-                            // our buffer so the cache can't be in this type
+        tokens: Vec<Token>,
+        index: OnceBox<Index>,
     }
 
     impl File {
         pub(crate) fn bytes_at(&self, span: Span) -> &BStr {
             &self.buf[span.to_range()].as_bstr()
         }
+
+        pub(crate) fn token(&self, at: usize) -> &Token {
+            &self.tokens[at]
+        }
+
+        /// Builds a `File` directly from its parts, skipping the (unimplemented) text parser -
+        /// only `File`'s own tests and `decode`'s need this, since both want a real `File` to
+        /// borrow from without parsing actual config text.
+        #[cfg(test)]
+        pub(crate) fn from_parts(buf: Vec<u8>, tokens: Vec<Token>) -> Self {
+            File {
+                buf,
+                tokens,
+                index: OnceBox::new(),
+            }
+        }
+
+        fn index(&self) -> &Index {
+            self.index.get_or_init(|| crate::no_std_prelude::Box::new(self.build_index()))
+        }
+
+        fn cmp_section(&self, a: (Span, Option<Span>), name: &[u8], sub_name: Option<&[u8]>) -> Ordering {
+            cmp_ci(self.bytes_at(a.0), name).then_with(|| cmp_sub_name(a.1.map(|s| self.bytes_at(s).as_bytes()), sub_name))
+        }
+
+        fn cmp_entry(&self, entry: &IndexEntry, name: &[u8], sub_name: Option<&[u8]>, key: &[u8]) -> Ordering {
+            self.cmp_section((entry.section, entry.sub_name), name, sub_name)
+                .then_with(|| cmp_ci(self.bytes_at(entry.key), key))
+        }
+
+        fn build_index(&self) -> Index {
+            let mut index = Index::default();
+            let mut current_section: Option<(usize, spanned::Section)> = None;
+            for (at, token) in self.tokens.iter().enumerate() {
+                match token {
+                    Token::Section(section) => {
+                        index.sections.push((section.name, section.sub_name));
+                        current_section = Some((at, *section));
+                    }
+                    Token::Entry(entry) => {
+                        if let Some((section_at, section)) = &current_section {
+                            index.entries.push(IndexEntry {
+                                section: section.name,
+                                sub_name: section.sub_name,
+                                key: entry.name,
+                                section_token: *section_at,
+                                entry_token: at,
+                            });
+                        }
+                    }
+                    Token::Comment(_) => {}
+                }
+            }
+            index
+                .sections
+                .sort_by(|&(a_name, a_sub), &(b_name, b_sub)| {
+                    cmp_ci(self.bytes_at(a_name), self.bytes_at(b_name))
+                        .then_with(|| cmp_sub_name(a_sub.map(|s| self.bytes_at(s).as_bytes()), b_sub.map(|s| self.bytes_at(s).as_bytes())))
+                });
+            index.entries.sort_by(|a, b| {
+                self.cmp_section((a.section, a.sub_name), self.bytes_at(b.section), b.sub_name.map(|s| self.bytes_at(s).as_bytes()))
+                    .then_with(|| cmp_ci(self.bytes_at(a.key), self.bytes_at(b.key)))
+            });
+            index
+        }
+
+        /// Looks up a section by its name, which git treats case-insensitively, and, if given, its
+        /// subsection name, which git treats case-sensitively (e.g. `[remote "origin"]`). Returns
+        /// `None` if no such section was ever opened in this file.
+        pub fn section<'s>(&'s self, name: &'s str, sub_name: Option<&'s str>) -> Option<borrowed::Section<'s>> {
+            let sections = &self.index().sections;
+            sections
+                .binary_search_by(|&section| self.cmp_section(section, name.as_bytes(), sub_name.map(str::as_bytes)))
+                .ok()
+                .map(|_| borrowed::Section { parent: self, name, sub_name })
+        }
+
+        /// All token positions of entries providing a value for `(name, sub_name, key)`, in file order.
+        pub(crate) fn entries_by_key(&self, name: &str, sub_name: Option<&str>, key: &str) -> Vec<(usize, usize)> {
+            let name = name.as_bytes();
+            let sub_name = sub_name.map(str::as_bytes);
+            let key = key.as_bytes();
+            let entries = &self.index().entries;
+            let start = entries.partition_point(|e| self.cmp_entry(e, name, sub_name, key) == Ordering::Less);
+            entries[start..]
+                .iter()
+                .take_while(|e| self.cmp_entry(e, name, sub_name, key) == Ordering::Equal)
+                .map(|e| (e.section_token, e.entry_token))
+                .collect()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// Appends `s` to `buf` and returns the span it now occupies, so tests can build a `File`
+        /// and its token spans from the same source of truth instead of counting bytes by hand.
+        fn push(buf: &mut Vec<u8>, s: &str) -> Span {
+            let start = buf.len();
+            buf.extend_from_slice(s.as_bytes());
+            Span {
+                start,
+                end_inclusive: buf.len() - 1,
+            }
+        }
+
+        #[test]
+        fn section_lookup_folds_case_on_the_name_but_not_on_the_subsection() {
+            let mut buf = Vec::new();
+            push(&mut buf, "[");
+            let name = push(&mut buf, "Remote");
+            push(&mut buf, " \"");
+            let sub_name = push(&mut buf, "origin");
+            push(&mut buf, "\"]\n");
+            let key = push(&mut buf, "url");
+            push(&mut buf, " = ");
+            let value = push(&mut buf, "a");
+            push(&mut buf, "\n");
+
+            let tokens = Vec::from([
+                Token::Section(spanned::Section {
+                    name,
+                    sub_name: Some(sub_name),
+                }),
+                Token::Entry(spanned::Entry { name: key, value: Some(value) }),
+            ]);
+            let file = File::from_parts(buf, tokens);
+
+            assert!(
+                file.section("remote", Some("origin")).is_some(),
+                "the section name must be looked up case-insensitively"
+            );
+            assert!(
+                file.section("remote", Some("Origin")).is_none(),
+                "the subsection name must be looked up case-sensitively"
+            );
+            assert!(file.section("remote", None).is_none(), "a different subsection must not match");
+            assert!(file.section("other", None).is_none());
+        }
+
+        #[test]
+        fn values_returns_every_occurrence_in_file_order_and_value_returns_the_last() {
+            let mut buf = Vec::new();
+            push(&mut buf, "[");
+            let name = push(&mut buf, "a");
+            push(&mut buf, "]\n");
+            let key1 = push(&mut buf, "k");
+            push(&mut buf, " = ");
+            let value1 = push(&mut buf, "one");
+            push(&mut buf, "\n");
+            let key2 = push(&mut buf, "k");
+            push(&mut buf, " = ");
+            let value2 = push(&mut buf, "two");
+            push(&mut buf, "\n");
+
+            let tokens = Vec::from([
+                Token::Section(spanned::Section { name, sub_name: None }),
+                Token::Entry(spanned::Entry {
+                    name: key1,
+                    value: Some(value1),
+                }),
+                Token::Entry(spanned::Entry {
+                    name: key2,
+                    value: Some(value2),
+                }),
+            ]);
+            let file = File::from_parts(buf, tokens);
+            let section = file.section("a", None).expect("section was opened above");
+
+            let values = section.values("k");
+            assert_eq!(values.len(), 2, "both occurrences of the repeated key must be returned");
+            assert_eq!(file.bytes_at(values[0].value.unwrap()), "one");
+            assert_eq!(file.bytes_at(values[1].value.unwrap()), "two");
+            assert_eq!(
+                file.bytes_at(section.value("k").unwrap().value.unwrap()),
+                "two",
+                "a repeated key must resolve to its last occurrence"
+            );
+            assert!(section.values("missing").is_empty());
+        }
     }
 }
 
 mod value {
+    #[derive(PartialEq, Eq, Debug, Hash, Clone, Copy)]
     pub enum Color {
+        Normal,
+        Default,
+        Black,
+        BrightBlack,
         Red,
         BrightRed,
-        Ansi { r: u8, g: u8, c: u8 },
+        Green,
+        BrightGreen,
+        Yellow,
+        BrightYellow,
+        Blue,
+        BrightBlue,
+        Magenta,
+        BrightMagenta,
+        Cyan,
+        BrightCyan,
+        White,
+        BrightWhite,
+        /// One of the 256 ANSI color codes.
+        Ansi(u8),
+        Rgb { r: u8, g: u8, b: u8 },
     }
 
-    mod resolve {
-        use bstr::BStr;
+    /// Path resolution touches the filesystem (the current user's home directory) and therefore
+    /// needs `std`; it has no meaningful `alloc`-only equivalent.
+    #[cfg(feature = "std")]
+    pub(crate) mod resolve {
+        use bstr::{BStr, ByteSlice};
         use quick_error::quick_error;
         use std::path::PathBuf;
 
         quick_error! {
             #[derive(Debug)]
             pub enum Error {
-                Tbd {
-                    display("TBD")
+                Utf8 {
+                    display("The path is not valid UTF-8 and can't be resolved")
+                }
+                Home {
+                    display("The home directory could not be obtained")
+                }
+                HomeFor(user: String) {
+                    display("Could not resolve the home directory of user '{}'", user)
                 }
             }
         }
-        pub fn path(_value: &BStr) -> Result<PathBuf, Error> {
-            unimplemented!("path_resolve")
+
+        fn to_utf8(bytes: &[u8]) -> Result<&str, Error> {
+            std::str::from_utf8(bytes).map_err(|_| Error::Utf8)
+        }
+
+        fn home_dir() -> Option<PathBuf> {
+            if cfg!(windows) {
+                std::env::var_os("USERPROFILE").map(PathBuf::from)
+            } else {
+                std::env::var_os("HOME").map(PathBuf::from)
+            }
+        }
+
+        /// Performs git's `~/` home-directory expansion on `value`, leaving paths that don't start
+        /// with `~` untouched. Another user's home (`~user/`) is not resolved: it fails with
+        /// [`Error::HomeFor`].
+        pub fn path(value: &BStr) -> Result<PathBuf, Error> {
+            if !value.starts_with(b"~") {
+                return Ok(PathBuf::from(to_utf8(value)?));
+            }
+
+            let (user, rest) = match value.find_byte(b'/') {
+                Some(slash) => (&value[1..slash], &value[slash + 1..]),
+                None => (&value[1..], &b""[..]),
+            };
+
+            let mut home = if user.is_empty() {
+                home_dir().ok_or(Error::Home)?
+            } else {
+                // Resolving another user's home directory requires a platform user-database
+                // lookup (e.g. `getpwnam` on unix) that we deliberately don't perform here.
+                return Err(Error::HomeFor(to_utf8(user)?.to_owned()));
+            };
+            if !rest.is_empty() {
+                home.push(to_utf8(rest)?);
+            }
+            Ok(home)
         }
     }
 }
 
 mod decode {
-    use crate::{borrowed, value};
-    use bstr::BStr;
-    use quick_error::quick_error;
-    use std::{borrow::Cow, path::PathBuf};
+    use crate::{
+        borrowed,
+        no_std_prelude::{Cow, Vec},
+        value,
+    };
+    use bstr::{BStr, BString};
+    #[cfg(feature = "std")]
+    use std::path::PathBuf;
+
+    /// Hand-rolled rather than built with `quick_error!`: that macro always emits an unconditional
+    /// `impl std::error::Error`, which doesn't exist under `no_std`, and gating individual variants
+    /// with `#[cfg(feature = "std")]` doesn't change that the macro invocation itself needs `std`.
+    #[derive(Debug)]
+    pub enum Error {
+        NoValue,
+        InvalidEscapeSequence(u8),
+        UnterminatedQuote,
+        InvalidBoolean(BString),
+        InvalidInteger(BString),
+        IntegerOverflow,
+        InvalidColor(BString),
+        #[cfg(feature = "std")]
+        Path(value::resolve::Error),
+        /// Yielded by [`borrowed::Entry::as_path`] when built without the `std` feature, where
+        /// there is no filesystem to resolve a path against in the first place.
+        #[cfg(not(feature = "std"))]
+        Io(crate::io::Error),
+    }
 
-    quick_error! {
-        #[derive(Debug)]
-        pub enum Error {
-            Tbd {
-                display("let's see what can go wrong and how we do it")
+    impl core::fmt::Display for Error {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            match self {
+                Error::NoValue => f.write_str("Entry has no value (TODO: much more error information)"),
+                Error::InvalidEscapeSequence(c) => write!(f, "Invalid escape sequence '\\{}' in quoted value", *c as char),
+                Error::UnterminatedQuote => f.write_str("Quoted value is missing its closing quote"),
+                Error::InvalidBoolean(input) => write!(f, "'{}' is not a valid boolean value", input),
+                Error::InvalidInteger(input) => write!(f, "'{}' is not a valid integer", input),
+                Error::IntegerOverflow => f.write_str("The integer value overflowed while applying its unit suffix"),
+                Error::InvalidColor(input) => write!(f, "'{}' is not a valid color value", input),
+                #[cfg(feature = "std")]
+                Error::Path(_) => f.write_str("The path could not be resolved"),
+                #[cfg(not(feature = "std"))]
+                Error::Io(err) => core::fmt::Display::fmt(err, f),
             }
-            NoValue {
-                display("Entry has no value (TODO: much more error information)")
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl std::error::Error for Error {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            match self {
+                Error::Path(err) => Some(err),
+                _ => None,
             }
         }
     }
 
-    pub fn value(_input: &BStr) -> Result<Cow<'_, BStr>, Error> {
-        unimplemented!("decode value from bstr")
+    #[cfg(feature = "std")]
+    impl From<value::resolve::Error> for Error {
+        fn from(err: value::resolve::Error) -> Self {
+            Error::Path(err)
+        }
+    }
+
+    /// Unquotes and unescapes a raw config value. Quoted and unquoted sections may be mixed
+    /// within the same value (e.g. `one\"two three\"` is valid), and escape sequences are
+    /// recognized both inside and outside of quotes.
+    pub fn value(input: &BStr) -> Result<Cow<'_, BStr>, Error> {
+        if !input.contains(&b'"') && !input.contains(&b'\\') {
+            return Ok(Cow::Borrowed(input));
+        }
+
+        let mut out = Vec::with_capacity(input.len());
+        let mut in_quotes = false;
+        let mut bytes = input.iter().copied();
+        while let Some(b) = bytes.next() {
+            match b {
+                b'"' => in_quotes = !in_quotes,
+                b'\\' => out.push(match bytes.next() {
+                    Some(b'\\') => b'\\',
+                    Some(b'"') => b'"',
+                    Some(b'n') => b'\n',
+                    Some(b't') => b'\t',
+                    Some(b'b') => 0x08,
+                    Some(other) => return Err(Error::InvalidEscapeSequence(other)),
+                    None => return Err(Error::UnterminatedQuote),
+                }),
+                other => out.push(other),
+            }
+        }
+        if in_quotes {
+            return Err(Error::UnterminatedQuote);
+        }
+        Ok(Cow::Owned(BString::from(out)))
     }
 
     impl<'a> borrowed::Entry<'a> {
         pub fn as_string(&self) -> Result<Cow<'a, BStr>, Error> {
             value(self.parent.bytes_at(self.value.ok_or_else(|| Error::NoValue)?)).map_err(Into::into)
         }
+
+        /// Interprets the value as an integer, applying git's `k`/`m`/`g` unit suffixes
+        /// (multiplying by 1024, 1024² and 1024³ respectively) before returning it.
         pub fn as_int(&self) -> Result<i64, Error> {
-            unimplemented!("as int")
+            let value = self.as_string()?;
+            let bytes: &[u8] = value.as_ref();
+            let (digits, multiplier) = match bytes.last() {
+                Some(b'k') | Some(b'K') => (&bytes[..bytes.len() - 1], 1024i64),
+                Some(b'm') | Some(b'M') => (&bytes[..bytes.len() - 1], 1024 * 1024),
+                Some(b'g') | Some(b'G') => (&bytes[..bytes.len() - 1], 1024 * 1024 * 1024),
+                _ => (bytes, 1),
+            };
+            let to_err = || Error::InvalidInteger(bytes.into());
+            let digits = core::str::from_utf8(digits).map_err(|_| to_err())?;
+            let base: i64 = digits.trim().parse().map_err(|_| to_err())?;
+            base.checked_mul(multiplier).ok_or(Error::IntegerOverflow)
         }
+
+        /// Interprets the value as a boolean. A valueless key (`[foo] bar` with no `= ...`) is
+        /// `true`; otherwise `true/false`, `yes/no`, `on/off` and `1/0` are accepted case-insensitively.
         pub fn as_bool(&self) -> Result<bool, Error> {
-            unimplemented!("as bool")
+            if self.value.is_none() {
+                return Ok(true);
+            }
+            let value = self.as_string()?;
+            let bytes: &[u8] = value.as_ref();
+            if bytes.eq_ignore_ascii_case(b"true") || bytes.eq_ignore_ascii_case(b"yes") || bytes.eq_ignore_ascii_case(b"on") || bytes == b"1" {
+                Ok(true)
+            } else if bytes.eq_ignore_ascii_case(b"false") || bytes.eq_ignore_ascii_case(b"no") || bytes.eq_ignore_ascii_case(b"off") || bytes == b"0" {
+                Ok(false)
+            } else {
+                Err(Error::InvalidBoolean(bytes.into()))
+            }
         }
+
+        /// Interprets the value as a path, expanding a leading `~/` the way git does. A leading
+        /// `~user/` is not expanded and yields [`Error::Path`].
+        #[cfg(feature = "std")]
         pub fn as_path(&self) -> Result<PathBuf, Error> {
-            unimplemented!("as bool")
+            let value = self.as_string()?;
+            value::resolve::path(value.as_ref()).map_err(Into::into)
         }
+
+        /// Without the `std` feature there is no filesystem to resolve `~` against, so this never
+        /// returns `Ok` - it still validates the value and fails with [`Error::Io`]. The `Ok` type
+        /// is `Infallible` rather than `PathBuf` since it can never actually be produced; callers
+        /// generic over the `std` feature still need to `#[cfg]` the success type at the call site.
+        #[cfg(not(feature = "std"))]
+        pub fn as_path(&self) -> Result<core::convert::Infallible, Error> {
+            self.as_string()?;
+            Err(Error::Io(crate::io::Error))
+        }
+
+        /// Interprets the value as a git color spec: a named color (optionally `bright`-prefixed),
+        /// an ANSI color code `0`-`255`, or an `#rrggbb` hex triplet.
         pub fn as_color(&self) -> Result<value::Color, Error> {
-            unimplemented!("as bool")
+            let value = self.as_string()?;
+            let bytes: &[u8] = value.as_ref();
+            let invalid = || Error::InvalidColor(bytes.into());
+
+            if let Some(hex) = bytes.strip_prefix(b"#") {
+                if hex.len() == 6 && hex.iter().all(u8::is_ascii_hexdigit) {
+                    let channel = |range: core::ops::Range<usize>| {
+                        u8::from_str_radix(core::str::from_utf8(&hex[range]).expect("ascii hex"), 16).expect("valid hex")
+                    };
+                    return Ok(value::Color::Rgb {
+                        r: channel(0..2),
+                        g: channel(2..4),
+                        b: channel(4..6),
+                    });
+                }
+                return Err(invalid());
+            }
+
+            if let Ok(text) = core::str::from_utf8(bytes) {
+                if let Ok(code) = text.parse::<u8>() {
+                    return Ok(value::Color::Ansi(code));
+                }
+            }
+
+            let lower = bytes.to_ascii_lowercase();
+            let (bright, name) = match lower.strip_prefix(b"bright") {
+                Some(rest) => (true, rest),
+                None => (false, &lower[..]),
+            };
+            use value::Color::*;
+            Ok(match (bright, name) {
+                (false, b"normal") => Normal,
+                (false, b"default") => Default,
+                (false, b"black") => Black,
+                (true, b"black") => BrightBlack,
+                (false, b"red") => Red,
+                (true, b"red") => BrightRed,
+                (false, b"green") => Green,
+                (true, b"green") => BrightGreen,
+                (false, b"yellow") => Yellow,
+                (true, b"yellow") => BrightYellow,
+                (false, b"blue") => Blue,
+                (true, b"blue") => BrightBlue,
+                (false, b"magenta") => Magenta,
+                (true, b"magenta") => BrightMagenta,
+                (false, b"cyan") => Cyan,
+                (true, b"cyan") => BrightCyan,
+                (false, b"white") => White,
+                (true, b"white") => BrightWhite,
+                _ => return Err(invalid()),
+            })
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::{file::File, spanned, Span};
+
+        /// A dummy span never read by any of the methods under test, since they only ever look at
+        /// `Entry::value`, not its section or name.
+        const UNUSED: Span = Span {
+            start: 0,
+            end_inclusive: 0,
+        };
+
+        fn entry_with_value(value: &str) -> (File, Span) {
+            let buf = value.as_bytes().to_vec();
+            let span = Span {
+                start: 0,
+                end_inclusive: value.len().saturating_sub(1),
+            };
+            (File::from_parts(buf, Vec::new()), span)
+        }
+
+        fn entry<'a>(file: &'a File, value: Option<Span>) -> borrowed::Entry<'a> {
+            borrowed::Entry::new(
+                file,
+                spanned::Section {
+                    name: UNUSED,
+                    sub_name: None,
+                },
+                UNUSED,
+                value,
+            )
+        }
+
+        #[test]
+        fn as_int_applies_unit_suffixes_and_reports_overflow() {
+            let (file, span) = entry_with_value("1k");
+            assert_eq!(entry(&file, Some(span)).as_int().unwrap(), 1024);
+
+            let (file, span) = entry_with_value("2M");
+            assert_eq!(entry(&file, Some(span)).as_int().unwrap(), 2 * 1024 * 1024);
+
+            let (file, span) = entry_with_value("not a number");
+            assert!(matches!(entry(&file, Some(span)).as_int(), Err(Error::InvalidInteger(_))));
+
+            let (file, span) = entry_with_value("9223372036854775807g");
+            assert!(matches!(entry(&file, Some(span)).as_int(), Err(Error::IntegerOverflow)));
+        }
+
+        #[test]
+        fn as_bool_is_true_for_a_valueless_key_and_accepts_gits_synonyms_case_insensitively() {
+            let (file, _) = entry_with_value("");
+            assert!(entry(&file, None).as_bool().unwrap(), "a valueless key is true");
+
+            for truthy in ["true", "Yes", "ON", "1"] {
+                let (file, span) = entry_with_value(truthy);
+                assert!(entry(&file, Some(span)).as_bool().unwrap(), "{truthy} must be true");
+            }
+            for falsy in ["false", "No", "OFF", "0"] {
+                let (file, span) = entry_with_value(falsy);
+                assert!(!entry(&file, Some(span)).as_bool().unwrap(), "{falsy} must be false");
+            }
+
+            let (file, span) = entry_with_value("maybe");
+            assert!(matches!(entry(&file, Some(span)).as_bool(), Err(Error::InvalidBoolean(_))));
+        }
+
+        #[test]
+        fn as_color_parses_hex_ansi_and_bright_named_colors() {
+            let (file, span) = entry_with_value("#ff00aa");
+            assert_eq!(
+                entry(&file, Some(span)).as_color().unwrap(),
+                value::Color::Rgb { r: 0xff, g: 0x00, b: 0xaa }
+            );
+
+            let (file, span) = entry_with_value("142");
+            assert_eq!(entry(&file, Some(span)).as_color().unwrap(), value::Color::Ansi(142));
+
+            let (file, span) = entry_with_value("brightGreen");
+            assert_eq!(entry(&file, Some(span)).as_color().unwrap(), value::Color::BrightGreen);
+
+            let (file, span) = entry_with_value("not-a-color");
+            assert!(matches!(entry(&file, Some(span)).as_color(), Err(Error::InvalidColor(_))));
         }
     }
 }
@@ -122,6 +686,7 @@ mod spanned {
     // This means we auto-trim whitespace otherwise, which I consider a feature
     pub(crate) type Comment = Span;
 
+    #[derive(Clone, Copy)]
     pub(crate) struct Section {
         pub(crate) name: Span,
         pub(crate) sub_name: Option<Span>,
@@ -134,19 +699,67 @@ mod spanned {
 }
 
 mod borrowed {
-    use crate::{file::File, spanned, Span};
+    use crate::{
+        file::{File, Token},
+        no_std_prelude::Vec,
+        spanned, Span,
+    };
 
     pub struct Entry<'a> {
         pub(crate) parent: &'a File,
+        #[allow(dead_code)] // kept for the section name/sub_name a future `Entry::section()` would expose
         section: spanned::Section,
+        #[allow(dead_code)]
         name: Span,
         pub(crate) value: Option<Span>,
     }
 
-    struct Section<'a> {
-        parent: &'a File,
-        name: Span,
-        sub_name: Option<Span>,
-        entries: Vec<spanned::Entry>,
+    impl<'a> Entry<'a> {
+        pub(crate) fn new(parent: &'a File, section: spanned::Section, name: Span, value: Option<Span>) -> Self {
+            Entry {
+                parent,
+                section,
+                name,
+                value,
+            }
+        }
+    }
+
+    /// A view over one section of a [`File`], obtained through `File::section()`.
+    ///
+    /// Multiple `[section]` blocks with the same name (and, if any, subsection) are merged, the way
+    /// git itself treats them, so `values()` can return entries spread across several blocks.
+    pub struct Section<'a> {
+        pub(crate) parent: &'a File,
+        pub(crate) name: &'a str,
+        pub(crate) sub_name: Option<&'a str>,
+    }
+
+    impl<'a> Section<'a> {
+        /// Returns the last occurrence of `key` in this section, matching how `git config` resolves
+        /// a repeated key to its final value.
+        pub fn value(&self, key: &str) -> Option<Entry<'a>> {
+            self.values(key).pop()
+        }
+
+        /// Returns every occurrence of `key` in this section, in file order - useful for multivars
+        /// like `remote.origin.fetch`, which git allows to repeat.
+        pub fn values(&self, key: &str) -> Vec<Entry<'a>> {
+            self.parent
+                .entries_by_key(self.name, self.sub_name, key)
+                .into_iter()
+                .map(|(section_idx, entry_idx)| {
+                    let section = match self.parent.token(section_idx) {
+                        Token::Section(section) => *section,
+                        _ => unreachable!("the index only ever records section tokens here"),
+                    };
+                    let entry = match self.parent.token(entry_idx) {
+                        Token::Entry(entry) => entry,
+                        _ => unreachable!("the index only ever records entry tokens here"),
+                    };
+                    Entry::new(self.parent, section, entry.name, entry.value)
+                })
+                .collect()
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,57 @@
+//! Compares sequential, single-threaded pack streaming against the parallel, index-free traversal
+//! over a larger pack, to show the speedup `traverse_unindexed_parallel` buys in exchange for a
+//! bounded worker pool. Point `GITOXIDE_BENCH_PACK` at a multi-hundred-MB pack file to exercise it
+//! meaningfully; no pack fixture is committed here (they're sizeable binary files), so without the
+//! env var set both benchmarks just print a notice and skip instead of failing the run.
+use criterion::{criterion_group, criterion_main, Criterion};
+use git_odb::pack::data::{
+    iter::parallel::{Ordering, Parallelism},
+    File,
+};
+use std::path::PathBuf;
+
+fn pack_path() -> Option<PathBuf> {
+    std::env::var_os("GITOXIDE_BENCH_PACK").map(PathBuf::from)
+}
+
+fn bench_sequential(c: &mut Criterion) {
+    let path = match pack_path() {
+        Some(path) => path,
+        None => {
+            eprintln!("skipping pack::data::File::iter (sequential): set GITOXIDE_BENCH_PACK to a pack file");
+            return;
+        }
+    };
+    c.bench_function("pack::data::File::iter (sequential)", |b| {
+        b.iter(|| {
+            let file = File::at(&path).expect("GITOXIDE_BENCH_PACK points at a valid pack file");
+            for entry in file.iter().expect("valid pack") {
+                entry.expect("entry decodes");
+            }
+        })
+    });
+}
+
+fn bench_parallel(c: &mut Criterion) {
+    let path = match pack_path() {
+        Some(path) => path,
+        None => {
+            eprintln!("skipping pack::data::File::traverse_unindexed_parallel: set GITOXIDE_BENCH_PACK to a pack file");
+            return;
+        }
+    };
+    c.bench_function("pack::data::File::traverse_unindexed_parallel", |b| {
+        b.iter(|| {
+            let file = File::at(&path).expect("GITOXIDE_BENCH_PACK points at a valid pack file");
+            let results = file
+                .traverse_unindexed_parallel(Ordering::Unordered, Parallelism::default())
+                .expect("valid pack");
+            for entry in results {
+                entry.expect("entry decodes");
+            }
+        })
+    });
+}
+
+criterion_group!(benches, bench_sequential, bench_parallel);
+criterion_main!(benches);
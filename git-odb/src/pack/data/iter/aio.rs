@@ -0,0 +1,278 @@
+//! An async counterpart to [`Iter`][super::Iter] for streaming pack entries off anything that implements
+//! `AsyncBufRead`, e.g. a network socket being fed by a fetch, without blocking a thread on I/O.
+//!
+//! The header parsing, per-entry inflate, trailer checksum and offset bookkeeping are identical to
+//! the blocking implementation - only the I/O driver differs, as polling instead of blocking is the
+//! only thing that actually needs to change between the two.
+use crate::{
+    pack,
+    pack::data::iter::Entry,
+    zlib::stream::inflate::{Inflate, Status},
+};
+use futures_io::AsyncBufRead;
+use git_features::hash::Sha1;
+use git_object::owned;
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+pub use super::Error;
+
+/// The phase a single entry's parsing is currently in. Shared in spirit with the blocking `Iter`,
+/// which runs through exactly the same progression but does so with blocking calls instead of
+/// yielding `Poll::Pending` back to the executor.
+enum State {
+    /// We haven't yet read the 12 byte pack header.
+    ReadPackHeader { buf: [u8; 12], filled: usize },
+    /// We are about to parse the next entry's header, having buffered its bytes read so far in `buf`.
+    ReadEntryHeader { buf: Vec<u8>, offset: u64 },
+    /// We know the entry's header and are inflating its body.
+    Inflate {
+        header: pack::data::Header,
+        header_size: u16,
+        pack_offset: u64,
+        decompressed_size: u64,
+        decompressor: Inflate,
+        compressed: Vec<u8>,
+        decompressed: Vec<u8>,
+    },
+    /// All entries have been produced; reading (and, if `verify` is set, checking) the trailing
+    /// SHA1 before the stream can be considered exhausted.
+    ReadTrailer { buf: [u8; 20], filled: usize },
+    /// Nothing left to parse, either because we are done or because an error occurred.
+    Depleted,
+}
+
+/// Like [`pack::data::iter::Iter`], but parses a pack asynchronously off an [`AsyncBufRead`], yielding
+/// [`Entry`] values as a `Stream` instead of a blocking `Iterator`.
+///
+/// Note that `read` is expected to start at the beginning of a valid pack file with header and trailer,
+/// exactly like the blocking counterpart.
+pub struct Iter<R> {
+    read: R,
+    state: State,
+    kind: Option<pack::data::Kind>,
+    objects_left: u32,
+    /// Every byte actually consumed from `read` so far, except for the trailer itself, fed into a
+    /// rolling hash so the trailing checksum can be produced (and verified) without a second pass.
+    running_hash: Sha1,
+    hash: Option<owned::Id>,
+    verify: bool,
+}
+
+impl<R> Iter<R>
+where
+    R: AsyncBufRead + Unpin,
+{
+    /// Create a new async iterator from an [`AsyncBufRead`] positioned at the start of a pack.
+    ///
+    /// If `verify` is true, the trailing pack checksum will be validated once the last entry has
+    /// been produced, exactly as for the blocking [`Iter`][super::Iter].
+    pub fn new_from_header(read: R, verify: bool) -> Self {
+        Iter {
+            read,
+            state: State::ReadPackHeader {
+                buf: [0u8; 12],
+                filled: 0,
+            },
+            kind: None,
+            objects_left: 0,
+            running_hash: Sha1::default(),
+            hash: None,
+            verify,
+        }
+    }
+
+    /// Available once the pack header has been parsed, i.e. after the first item was yielded.
+    pub fn kind(&self) -> Option<pack::data::Kind> {
+        self.kind
+    }
+
+    /// Can only be queried once the stream has been exhausted, i.e. once `poll_next` has returned
+    /// `None`.
+    pub fn checksum(&self) -> owned::Id {
+        self.hash.expect("stream must be exhausted")
+    }
+}
+
+impl<R> futures_core::Stream for Iter<R>
+where
+    R: AsyncBufRead + Unpin,
+{
+    type Item = Result<Entry, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        if matches!(this.state, State::Depleted) {
+            return Poll::Ready(None);
+        }
+        loop {
+            match &mut this.state {
+                State::ReadPackHeader { buf, filled } => {
+                    let available = match Pin::new(&mut this.read).poll_fill_buf(cx) {
+                        Poll::Ready(Ok(available)) => available,
+                        Poll::Ready(Err(err)) => {
+                            this.state = State::Depleted;
+                            return Poll::Ready(Some(Err(err.into())));
+                        }
+                        Poll::Pending => return Poll::Pending,
+                    };
+                    if available.is_empty() {
+                        this.state = State::Depleted;
+                        return Poll::Ready(Some(Err(io::Error::from(io::ErrorKind::UnexpectedEof).into())));
+                    }
+                    let want = buf.len() - *filled;
+                    let take = want.min(available.len());
+                    buf[*filled..*filled + take].copy_from_slice(&available[..take]);
+                    this.running_hash.update(&available[..take]);
+                    *filled += take;
+                    Pin::new(&mut this.read).consume(take);
+                    if *filled == buf.len() {
+                        let (kind, num_objects) = pack::data::parse::header(buf)?;
+                        this.kind = Some(kind);
+                        this.objects_left = num_objects;
+                        this.state = if num_objects == 0 {
+                            State::ReadTrailer {
+                                buf: [0u8; 20],
+                                filled: 0,
+                            }
+                        } else {
+                            State::ReadEntryHeader {
+                                buf: Vec::new(),
+                                offset: buf.len() as u64,
+                            }
+                        };
+                    }
+                }
+                State::ReadEntryHeader { buf, offset } => {
+                    let available = match Pin::new(&mut this.read).poll_fill_buf(cx) {
+                        Poll::Ready(Ok(available)) => available,
+                        Poll::Ready(Err(err)) => {
+                            this.state = State::Depleted;
+                            return Poll::Ready(Some(Err(err.into())));
+                        }
+                        Poll::Pending => return Poll::Pending,
+                    };
+                    if available.is_empty() {
+                        this.state = State::Depleted;
+                        return Poll::Ready(Some(Err(io::Error::from(io::ErrorKind::UnexpectedEof).into())));
+                    }
+                    buf.push(available[0]);
+                    this.running_hash.update(&available[..1]);
+                    Pin::new(&mut this.read).consume(1);
+                    match pack::data::Header::from_bytes(buf, *offset) {
+                        Ok((header, decompressed_size, header_size)) => {
+                            this.state = State::Inflate {
+                                header,
+                                header_size: header_size as u16,
+                                pack_offset: *offset,
+                                decompressed_size,
+                                decompressor: Inflate::default(),
+                                compressed: Vec::new(),
+                                decompressed: Vec::with_capacity(decompressed_size as usize),
+                            };
+                        }
+                        Err(pack::data::parse::Error::NeedMoreBytes) => {}
+                        Err(err) => {
+                            this.state = State::Depleted;
+                            return Poll::Ready(Some(Err(err.into())));
+                        }
+                    }
+                }
+                State::Inflate {
+                    header,
+                    header_size,
+                    pack_offset,
+                    decompressed_size,
+                    decompressor,
+                    compressed,
+                    decompressed,
+                } => {
+                    let available = match Pin::new(&mut this.read).poll_fill_buf(cx) {
+                        Poll::Ready(Ok(available)) => available,
+                        Poll::Ready(Err(err)) => {
+                            this.state = State::Depleted;
+                            return Poll::Ready(Some(Err(err.into())));
+                        }
+                        Poll::Pending => return Poll::Pending,
+                    };
+                    if available.is_empty() {
+                        this.state = State::Depleted;
+                        return Poll::Ready(Some(Err(io::Error::from(io::ErrorKind::UnexpectedEof).into())));
+                    }
+                    let before_in = decompressor.total_in;
+                    let status = decompressor.decompress(available, decompressed)?;
+                    let consumed = (decompressor.total_in - before_in) as usize;
+                    compressed.extend_from_slice(&available[..consumed]);
+                    this.running_hash.update(&available[..consumed]);
+                    Pin::new(&mut this.read).consume(consumed);
+
+                    if status == Status::StreamEnd {
+                        let entry = Entry {
+                            header: header.clone(),
+                            header_size: *header_size,
+                            pack_offset: *pack_offset,
+                            compressed: std::mem::take(compressed),
+                            decompressed: std::mem::take(decompressed),
+                        };
+                        let next_offset = *pack_offset + *header_size as u64 + decompressor.total_in;
+                        this.objects_left -= 1;
+                        this.state = if this.objects_left == 0 {
+                            State::ReadTrailer {
+                                buf: [0u8; 20],
+                                filled: 0,
+                            }
+                        } else {
+                            State::ReadEntryHeader {
+                                buf: Vec::new(),
+                                offset: next_offset,
+                            }
+                        };
+                        return Poll::Ready(Some(Ok(entry)));
+                    }
+                }
+                State::ReadTrailer { buf, filled } => {
+                    let available = match Pin::new(&mut this.read).poll_fill_buf(cx) {
+                        Poll::Ready(Ok(available)) => available,
+                        Poll::Ready(Err(err)) => {
+                            this.state = State::Depleted;
+                            return Poll::Ready(Some(Err(err.into())));
+                        }
+                        Poll::Pending => return Poll::Pending,
+                    };
+                    if available.is_empty() {
+                        this.state = State::Depleted;
+                        return Poll::Ready(Some(Err(io::Error::from(io::ErrorKind::UnexpectedEof).into())));
+                    }
+                    let want = buf.len() - *filled;
+                    let take = want.min(available.len());
+                    // The trailer is the digest itself and was never meant to be hashed, so unlike
+                    // every other state above we deliberately don't feed these bytes to `running_hash`.
+                    buf[*filled..*filled + take].copy_from_slice(&available[..take]);
+                    *filled += take;
+                    Pin::new(&mut this.read).consume(take);
+                    if *filled == buf.len() {
+                        let expected = owned::Id::from_20_bytes(buf);
+                        let actual = std::mem::replace(&mut this.running_hash, Sha1::default()).digest();
+                        if this.verify {
+                            let actual_id = owned::Id::from_20_bytes(&actual);
+                            if actual_id != expected {
+                                this.state = State::Depleted;
+                                return Poll::Ready(Some(Err(Error::Checksum {
+                                    expected,
+                                    actual: actual_id,
+                                })));
+                            }
+                        }
+                        this.hash = Some(expected);
+                        this.state = State::Depleted;
+                        return Poll::Ready(None);
+                    }
+                }
+                State::Depleted => return Poll::Ready(None),
+            }
+        }
+    }
+}
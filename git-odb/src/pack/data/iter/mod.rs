@@ -0,0 +1,465 @@
+use crate::{
+    pack,
+    zlib::stream::{inflate::Inflate, InflateReader},
+};
+use git_features::hash::Sha1;
+use git_object::owned;
+use quick_error::quick_error;
+use std::{fs, io};
+
+#[cfg(feature = "async")]
+pub mod aio;
+pub mod parallel;
+
+quick_error! {
+    #[derive(Debug)]
+    pub enum Error {
+        Io(err: io::Error) {
+            display("An IO operation failed while streaming an entry")
+            from()
+            source(err)
+        }
+        PackParse(err: pack::data::parse::Error) {
+            display("The pack header could not be parsed")
+            from()
+            source(err)
+        }
+        Checksum { expected: owned::Id, actual: owned::Id } {
+            display("The SHA1 of {} did not match the expected {}", actual, expected)
+        }
+        Overread { consumed: u64, expected: u64 } {
+            display("Replaying the {} bytes captured for an entry should have consumed exactly that many bytes, but only consumed {} - we must have overread into the following entry's header", expected, consumed)
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Entry {
+    pub header: pack::data::Header,
+    /// amount of bytes used to encode the `header`. `pack_offset + header_size` is the beginning of the compressed data in the pack.
+    pub header_size: u16,
+    pub pack_offset: u64,
+    /// amount bytes consumed while producing `decompressed`
+    pub compressed: Vec<u8>,
+    /// The decompressed data.
+    pub decompressed: Vec<u8>,
+}
+
+pub struct Iter<R> {
+    read: HashingRead<R>,
+    decompressor: Option<Inflate>,
+    offset: u64,
+    had_error: bool,
+    kind: pack::data::Kind,
+    objects_left: u32,
+    hash: Option<owned::Id>,
+    verify: bool,
+}
+
+impl<R> Iter<R>
+where
+    R: io::BufRead,
+{
+    /// Note that `read` is expected at the beginning of a valid pack file with header and trailer
+    /// If `verify` is true, the SHA1 is checked once the last entry was decoded, and a mismatch is yielded
+    /// as [`Error::Checksum`] in place of that entry.
+    /// Otherwise bit there is a chance that some kinds of bitrot or inconsistencies will not be detected.
+    pub fn new_from_header(read: R, verify: bool) -> Result<Iter<R>, Error> {
+        let mut read = HashingRead {
+            read,
+            hash: Sha1::default(),
+        };
+        let mut header_data = [0u8; 12];
+        read.read_exact(&mut header_data)?;
+
+        let (kind, num_objects) = pack::data::parse::header(&header_data)?;
+        assert_eq!(
+            kind,
+            pack::data::Kind::V2,
+            "let's stop here if we see undocumented pack formats"
+        );
+        Ok(Iter {
+            read,
+            decompressor: None,
+            offset: 12,
+            had_error: false,
+            kind,
+            objects_left: num_objects,
+            hash: None,
+            verify,
+        })
+    }
+
+    pub fn kind(&self) -> pack::data::Kind {
+        self.kind
+    }
+
+    /// Can only be queried once the iterator has been exhausted and `len()` returns 0
+    pub fn checksum(&self) -> owned::Id {
+        self.hash.expect("iterator must be exhausted")
+    }
+
+    /// Reads and validates (if `verify` is set) the trailing pack checksum, called once the last
+    /// entry has been produced. The trailer bytes themselves are read directly off the underlying
+    /// reader, bypassing the hasher, since they are the digest and were never meant to be hashed.
+    fn read_trailer(&mut self) -> Result<(), Error> {
+        let actual = std::mem::replace(&mut self.read.hash, Sha1::default()).digest();
+        let mut trailer = [0u8; 20];
+        self.read.read.read_exact(&mut trailer)?;
+        let expected = owned::Id::from_20_bytes(&trailer);
+
+        if self.verify {
+            let actual_id = owned::Id::from_20_bytes(&actual);
+            if actual_id != expected {
+                return Err(Error::Checksum {
+                    expected,
+                    actual: actual_id,
+                });
+            }
+        }
+        self.hash = Some(expected);
+        Ok(())
+    }
+
+    fn next_inner(&mut self) -> Result<Entry, Error> {
+        let (header, decompressed_size, header_size) =
+            pack::data::Header::from_read(&mut self.read, self.offset).map_err(Error::from)?;
+
+        let mut decompressor = self.decompressor.take().unwrap_or_default();
+        decompressor.reset();
+        let mut reader = InflateReader {
+            inner: PassThrough {
+                read: &mut self.read,
+                write: Vec::with_capacity((decompressed_size / 2) as usize),
+            },
+            decompressor,
+        };
+
+        let mut decompressed = Vec::with_capacity(decompressed_size as usize);
+        let bytes_copied = io::copy(&mut reader, &mut decompressed)?;
+
+        assert_eq!(
+            bytes_copied, decompressed_size,
+            "We should have decompressed {} bytes, but got {} instead",
+            decompressed_size, bytes_copied
+        );
+
+        let pack_offset = self.offset;
+        let compressed_size = reader.decompressor.total_in;
+        self.offset += header_size as u64 + compressed_size;
+        self.decompressor = Some(reader.decompressor);
+        let mut compressed = reader.inner.write;
+        compressed.shrink_to_fit();
+        assert_eq!(
+            compressed_size,
+            compressed.len() as u64,
+            "we must track exactly the same amount of bytes as read by the decompressor"
+        );
+        // This isn't implied by the two `assert_eq!`s above: those only check that our own framing
+        // logic agrees with the decompressor's own byte-count, not that the framing was actually
+        // correct. A corrupt or adversarial pack arriving over a pipe or socket (where we can't seek
+        // back and re-check) could still desync the stream here, so unlike the panicking checks
+        // above we report this one as a recoverable error.
+        let mut verify_reader = InflateReader {
+            inner: io::Cursor::new(compressed.as_slice()),
+            decompressor: Inflate::default(),
+        };
+        io::copy(&mut verify_reader, &mut io::sink())?;
+        if verify_reader.decompressor.total_in != compressed.len() as u64 {
+            return Err(Error::Overread {
+                consumed: verify_reader.decompressor.total_in,
+                expected: compressed.len() as u64,
+            });
+        }
+
+        Ok(Entry {
+            header,
+            // TODO: remove this field once we can pack-encode the header above
+            header_size: header_size as u16,
+            compressed,
+            pack_offset,
+            decompressed,
+        })
+    }
+}
+
+impl<R> Iterator for Iter<R>
+where
+    R: io::BufRead,
+{
+    type Item = Result<Entry, Error>;
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.objects_left as usize, Some(self.objects_left as usize))
+    }
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.had_error {
+            return None;
+        }
+        if self.objects_left == 0 {
+            // A pack with zero objects still has a trailer right after the header, and we must
+            // read (and, if `verify` is set, check) it here - this is the only call site an
+            // all-empty pack ever reaches - or `checksum()` would panic despite the iterator
+            // correctly reporting itself exhausted via `size_hint()`/`len()`.
+            if self.hash.is_none() {
+                if let Err(err) = self.read_trailer() {
+                    self.had_error = true;
+                    return Some(Err(err));
+                }
+            }
+            return None;
+        }
+        self.objects_left -= 1; // even an error counts as objects
+        let result = self.next_inner();
+        self.had_error = result.is_err();
+        if self.had_error {
+            return Some(result);
+        }
+        if self.objects_left == 0 {
+            if let Err(err) = self.read_trailer() {
+                self.had_error = true;
+                return Some(Err(err));
+            }
+        }
+        Some(result)
+    }
+}
+impl<R> std::iter::ExactSizeIterator for Iter<R> where R: io::BufRead {}
+
+/// Wraps a `BufRead` and feeds every byte actually consumed from it into a rolling SHA1, so the
+/// pack's trailing checksum can be verified (and returned) without a second, dedicated pass over
+/// the stream. Bytes are only hashed once `consume`d, mirroring [`PassThrough`] below, so a `fill_buf`
+/// peek that nothing ends up using - because an entry's header turned out to need more bytes to
+/// parse, say - is never double-counted.
+struct HashingRead<R> {
+    read: R,
+    hash: Sha1,
+}
+
+impl<R> io::Read for HashingRead<R>
+where
+    R: io::Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let bytes_read = self.read.read(buf)?;
+        self.hash.update(&buf[..bytes_read]);
+        Ok(bytes_read)
+    }
+}
+
+impl<R> io::BufRead for HashingRead<R>
+where
+    R: io::BufRead,
+{
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.read.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let buf = self
+            .read
+            .fill_buf()
+            .expect("never fail as we called fill-buf before and this does nothing");
+        self.hash.update(&buf[..amt]);
+        self.read.consume(amt)
+    }
+}
+
+/// A `BufRead` that mirrors every byte it hands out to a decompressor into `write`, but only once
+/// that decompressor actually reports having consumed it.
+///
+/// This is what makes framing exact even on non-seekable sources like pipes or sockets: `fill_buf`
+/// may return more bytes than belong to the current entry (whatever is sitting in the underlying
+/// reader's buffer), but `consume` is only ever called by the decompressor with the number of bytes
+/// it truly used, and only those are recorded and removed from the underlying reader. Any surplus
+/// bytes handed out by a previous `fill_buf` call but never `consume`d simply remain buffered in
+/// `read`, ready to be served as the start of the next entry - there is nothing to push back
+/// because we never took them out in the first place.
+///
+/// This struct only accounts for bytes the decompressor *tells us* it used; it cannot by itself
+/// catch a decompressor that miscounts and consumes bytes belonging to the next entry. `next_inner`
+/// guards against that independently by replaying the captured bytes through a fresh decompressor.
+struct PassThrough<R, W> {
+    read: R,
+    write: W,
+}
+
+impl<R, W> io::BufRead for PassThrough<R, W>
+where
+    Self: io::Read,
+    R: io::BufRead,
+    W: io::Write,
+{
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.read.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let buf = self
+            .read
+            .fill_buf()
+            .expect("never fail as we called fill-buf before and this does nothing");
+        self.write
+            .write_all(&buf[..amt])
+            .expect("a write to never fail - should be a memory buffer");
+        self.read.consume(amt);
+    }
+}
+
+impl<R, W> io::Read for PassThrough<R, W>
+where
+    R: io::Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read.read(buf)
+    }
+}
+
+impl pack::data::File {
+    /// Returns an iterator over the pack file itself, without making use of the memory mapping.
+    ///
+    /// Note that this iterator is costly as no pack index is used, forcing each entry to be decompressed.
+    /// If an index is available, use the `traverse(…)` method instead for maximum performance.
+    pub fn iter(&self) -> Result<Iter<io::BufReader<fs::File>>, Error> {
+        let reader = io::BufReader::new(fs::File::open(&self.path)?);
+        Iter::new_from_header(reader, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HashingRead, Iter, PassThrough};
+    use crate::{
+        pack,
+        zlib::stream::{inflate::Inflate, InflateReader},
+    };
+    use flate2::{Compress, Compression, FlushCompress};
+    use git_features::hash::Sha1;
+    use git_object::owned;
+    use std::io::{self, BufRead, Read};
+
+    /// The regression this guards against: `next()` used to bail out on `objects_left == 0`
+    /// before ever reading the trailer, so a zero-object pack would report itself exhausted via
+    /// `size_hint()`/`len()` while `checksum()` still panicked - the trailer was simply never read.
+    #[test]
+    fn exhausting_a_zero_object_pack_still_reads_and_exposes_the_trailer() {
+        let expected = Sha1::default().digest();
+
+        let mut iter = Iter {
+            read: HashingRead {
+                read: io::Cursor::new(expected.to_vec()),
+                hash: Sha1::default(),
+            },
+            decompressor: None,
+            offset: 12,
+            had_error: false,
+            kind: pack::data::Kind::V2,
+            objects_left: 0,
+            hash: None,
+            verify: true,
+        };
+
+        assert!(iter.next().is_none(), "a zero-object pack has no entries to yield");
+        assert_eq!(
+            iter.checksum(),
+            owned::Id::from_20_bytes(&expected),
+            "the trailer must still be read and verified even though there was nothing to iterate"
+        );
+    }
+
+    /// A reader that only ever hands out a single byte at a time from `fill_buf`, no matter how
+    /// much the underlying source actually has buffered. This is the cheapest way to simulate a
+    /// pipe or socket, where callers cannot assume a whole entry (or even a whole header) arrives
+    /// in one `fill_buf` call.
+    struct OneByteAtATime<'a> {
+        data: &'a [u8],
+        pos: usize,
+    }
+
+    impl<'a> OneByteAtATime<'a> {
+        fn new(data: &'a [u8]) -> Self {
+            OneByteAtATime { data, pos: 0 }
+        }
+    }
+
+    impl<'a> Read for OneByteAtATime<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if buf.is_empty() || self.pos >= self.data.len() {
+                return Ok(0);
+            }
+            buf[0] = self.data[self.pos];
+            self.pos += 1;
+            Ok(1)
+        }
+    }
+
+    impl<'a> BufRead for OneByteAtATime<'a> {
+        fn fill_buf(&mut self) -> io::Result<&[u8]> {
+            Ok(if self.pos < self.data.len() {
+                &self.data[self.pos..self.pos + 1]
+            } else {
+                &[]
+            })
+        }
+        fn consume(&mut self, amt: usize) {
+            assert!(amt <= 1, "we only ever hand out a single byte at a time");
+            self.pos += amt;
+        }
+    }
+
+    /// Raw zlib-compresses `data`, the same stream shape a pack stores object bodies in.
+    fn zlib_compress(data: &[u8]) -> Vec<u8> {
+        let mut compressor = Compress::new(Compression::default(), true);
+        let mut out = Vec::with_capacity(data.len());
+        compressor
+            .compress_vec(data, &mut out, FlushCompress::Finish)
+            .expect("compressing an in-memory buffer never fails");
+        out
+    }
+
+    /// The regression this guards against: a decompressor that reads past the end of its own
+    /// deflate stream and into the bytes of the entry that follows. We drive `PassThrough` with a
+    /// real `Inflate`/`InflateReader`, fed one byte at a time so no single `fill_buf` call can ever
+    /// hand out more than the current entry owns - the same non-seekable-pipe scenario the framing
+    /// has to handle - then check that the following entry's bytes are still untouched and
+    /// independently decodable, which an overread would have corrupted or consumed.
+    #[test]
+    fn decompression_is_framed_exactly_to_its_entry_even_one_byte_at_a_time() {
+        let first = b"first entry payload".to_vec();
+        let second = b"second entry payload, long enough that no single read buffer could mask an overread".to_vec();
+        let first_compressed = zlib_compress(&first);
+        let second_compressed = zlib_compress(&second);
+
+        let mut source = Vec::new();
+        source.extend_from_slice(&first_compressed);
+        source.extend_from_slice(&second_compressed);
+        let mut chunked = OneByteAtATime::new(&source);
+
+        let mut reader = InflateReader {
+            inner: PassThrough {
+                read: &mut chunked,
+                write: Vec::new(),
+            },
+            decompressor: Inflate::default(),
+        };
+        let mut decompressed = Vec::new();
+        io::copy(&mut reader, &mut decompressed).expect("first entry is a valid zlib stream");
+
+        assert_eq!(decompressed, first, "must decompress to exactly the first entry's payload");
+        assert_eq!(
+            reader.inner.write, first_compressed,
+            "must capture exactly the first entry's compressed bytes, not a single one of the second's"
+        );
+
+        let mut next_reader = InflateReader {
+            inner: &mut chunked,
+            decompressor: Inflate::default(),
+        };
+        let mut next_decompressed = Vec::new();
+        io::copy(&mut next_reader, &mut next_decompressed)
+            .expect("second entry must still be a valid, completely untouched zlib stream");
+        assert_eq!(next_decompressed, second);
+    }
+}
\ No newline at end of file